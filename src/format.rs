@@ -0,0 +1,17 @@
+/// Serialization backend used to persist a `Menace` instance to disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Cbor
+}
+
+impl Format {
+    /// Infers the format from a file path's extension, defaulting to `Json`
+    /// for anything other than a `.cbor` extension
+    pub fn from_path(path: &str) -> Self {
+        match std::path::Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("cbor") => Format::Cbor,
+            _ => Format::Json
+        }
+    }
+}
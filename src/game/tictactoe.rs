@@ -2,21 +2,74 @@ use super::symbol::Symbol;
 use std::fmt::{Display, Formatter};
 use std::{fmt, usize};
 
-#[derive(Debug)]
+const DEFAULT_SIZE: usize = 3;
+const DEFAULT_WIN_LENGTH: usize = 3;
+
+/// The current status of a game, folding the separate `is_winner`/`is_draw`
+/// checks into a single result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    XMove,
+    OMove,
+    XWon,
+    OWon,
+    Draw
+}
+
+#[derive(Debug, Clone)]
 pub struct TicTacToe {
-    board: [Symbol; 9]
+    board: Vec<Symbol>,
+    n: usize,
+    k: usize,
+    win_lines: Vec<Vec<usize>>
 }
 
 impl TicTacToe {
+    /// Creates a standard 3x3, 3-in-a-row board
     pub fn new() -> Self {
-        Self {
-            board: [Symbol::EMPTY; 9]
+        Self::with_size(DEFAULT_SIZE, DEFAULT_WIN_LENGTH)
+            .expect("default board size/win length are always valid")
+    }
+
+    /// Creates an `n`x`n` board where `k` pieces in a row (horizontally,
+    /// vertically, or diagonally) are needed to win
+    ///
+    /// # Returns
+    ///
+    /// `Err(msg)` if `n` is `0` or `k` is not in `1..=n`
+    pub fn with_size(n: usize, k: usize) -> Result<Self, String> {
+        if n == 0 {
+            return Err("board size 'n' must be at least 1".to_string());
+        }
+        if k == 0 || k > n {
+            return Err(format!("win length 'k' must be between 1 and {}", n));
         }
+
+        Ok(Self {
+            board: vec![Symbol::EMPTY; n * n],
+            win_lines: Self::win_lines(n, k),
+            n,
+            k
+        })
+    }
+
+    /// # Returns
+    ///
+    /// The side length `n` of this board
+    pub fn size(&self) -> usize {
+        self.n
+    }
+
+    /// # Returns
+    ///
+    /// The number of pieces `k` in a row needed to win on this board
+    pub fn win_length(&self) -> usize {
+        self.k
     }
 
     /// Resets the game to it's initial state
     pub fn reset(&mut self) {
-        self.board = [Symbol::EMPTY; 9];
+        self.board = vec![Symbol::EMPTY; self.n * self.n];
     }
 
     /// Forces the game to a state based on the given 'flat_state'
@@ -31,23 +84,23 @@ impl TicTacToe {
     /// # Returns
     ///
     /// `Ok(())` if `action` is a legal move, `Err(msg)` otherwise
-    pub fn place_piece(&mut self, piece: Symbol, action: u8) -> Result<(), String> {
-        if self.board[action as usize] != Symbol::EMPTY {
+    pub fn place_piece(&mut self, piece: Symbol, action: usize) -> Result<(), String> {
+        if self.board[action] != Symbol::EMPTY {
             return Err(format!("'{}' is not an empty space!", action));
         }
-        self.board[action as usize] = piece;
+        self.board[action] = piece;
 
         Ok(())
     }
 
     /// # Returns
-    /// 
+    ///
     /// A list of the next possible flattened states the game could have
     pub fn next_states(&self, piece: Symbol) -> Vec<String> {
         self.legal_moves().iter().map(|action| {
             let mut board = self.board.clone();
 
-            board[*action as usize] = piece;
+            board[*action] = piece;
             board.iter().map(
                 |cell| cell.as_char()
             ).collect()
@@ -60,19 +113,8 @@ impl TicTacToe {
     ///
     /// `true` if the piece meets winning criteria, `false` otherwise
     pub fn is_winner(&self, piece: Symbol) -> bool {
-        let win_conds: [[u8; 3]; 8] = [
-            [0, 1, 2],
-            [3, 4, 5],
-            [6, 7, 8],
-            [0, 3, 6],
-            [1, 4, 7],
-            [2, 5, 8],
-            [0, 4, 8],
-            [2, 4, 6]
-        ];
-
-        for win_cond in win_conds.iter() {
-            if win_cond.iter().all(|&c| self.board[c as usize] == piece) {
+        for win_line in self.win_lines.iter() {
+            if win_line.iter().all(|&c| self.board[c] == piece) {
                 return true;
             }
         }
@@ -80,7 +122,7 @@ impl TicTacToe {
     }
 
     /// # Returns
-    /// 
+    ///
     /// 'true' if the game has ended in a draw, 'false' otherwise
     pub fn is_draw(&self) -> bool {
         for cell in self.board.iter() {
@@ -91,6 +133,33 @@ impl TicTacToe {
         true
     }
 
+    /// Determines the game's current status with one call, so callers need
+    /// not check `is_winner`/`is_draw` themselves
+    ///
+    /// # Returns
+    ///
+    /// The `State` the game is currently in
+    pub fn state(&self) -> State {
+        if self.is_winner(Symbol::X) {
+            return State::XWon;
+        }
+        if self.is_winner(Symbol::O) {
+            return State::OWon;
+        }
+        if self.is_draw() {
+            return State::Draw;
+        }
+
+        let x_count = self.board.iter().filter(|&&cell| cell == Symbol::X).count();
+        let o_count = self.board.iter().filter(|&&cell| cell == Symbol::O).count();
+
+        if x_count <= o_count {
+            State::XMove
+        } else {
+            State::OMove
+        }
+    }
+
     /// # Returns
     ///
     /// A flattened string representation of `board`
@@ -103,32 +172,121 @@ impl TicTacToe {
         flattened
     }
 
+    /// Computes the canonical form of this board's flattened state,
+    /// collapsing the 8 rotations/reflections of an `n`x`n` board into a
+    /// single representative key (the lexicographically smallest of them).
+    ///
+    /// # Returns
+    ///
+    /// `(canonical_flat, perm)` where `perm` is the index permutation that
+    /// produced `canonical_flat`, i.e. `canonical_flat[i] == self.flat()[perm[i]]`
+    /// for every index `i`. Applying `perm` to any other flattened state of
+    /// the same board size maps it into this same orientation, and applying
+    /// its functional inverse maps a canonical state back to this board's
+    /// original orientation.
+    pub fn canonical(&self) -> (String, Vec<usize>) {
+        let flat = self.flat();
+        let bytes = flat.as_bytes();
+
+        Self::symmetry_transforms(self.n).into_iter()
+            .map(|perm| {
+                let transformed: String = perm.iter().map(|&i| bytes[i] as char).collect();
+                (transformed, perm)
+            })
+            .min_by(|(a, _), (b, _)| a.cmp(b))
+            .unwrap()
+    }
+
+    /// All 8 index permutations of the dihedral group of the square for an
+    /// `n`x`n` board (identity, 3 rotations, 4 reflections)
+    fn symmetry_transforms(n: usize) -> Vec<Vec<usize>> {
+        let mut identity = Vec::with_capacity(n * n);
+        let mut rotate90 = Vec::with_capacity(n * n);
+        let mut rotate180 = Vec::with_capacity(n * n);
+        let mut rotate270 = Vec::with_capacity(n * n);
+        let mut flip_h = Vec::with_capacity(n * n);
+        let mut flip_v = Vec::with_capacity(n * n);
+        let mut transpose = Vec::with_capacity(n * n);
+        let mut anti_transpose = Vec::with_capacity(n * n);
+
+        for r in 0..n {
+            for c in 0..n {
+                identity.push(r * n + c);
+                rotate90.push((n - 1 - c) * n + r);
+                rotate180.push((n - 1 - r) * n + (n - 1 - c));
+                rotate270.push(c * n + (n - 1 - r));
+                flip_h.push(r * n + (n - 1 - c));
+                flip_v.push((n - 1 - r) * n + c);
+                transpose.push(c * n + r);
+                anti_transpose.push((n - 1 - c) * n + (n - 1 - r));
+            }
+        }
+        vec![identity, rotate90, rotate180, rotate270, flip_h, flip_v, transpose, anti_transpose]
+    }
+
     /// # Returns
     ///
     /// A list of all legal moves that can be made
-    fn legal_moves(&self) -> Vec<u8> {
-        let mut allowed_actions: Vec<u8> = Vec::with_capacity(9);
+    fn legal_moves(&self) -> Vec<usize> {
+        let mut allowed_actions: Vec<usize> = Vec::with_capacity(self.board.len());
 
         for (i, piece) in self.board.iter().enumerate() {
             if piece == &Symbol::EMPTY {
-                allowed_actions.push(i as u8);
+                allowed_actions.push(i);
             }
         }
         allowed_actions
     }
+
+    /// Computes every winning line (row, column, or diagonal of length `k`)
+    /// on an `n`x`n` board, as lists of flattened board indices
+    fn win_lines(n: usize, k: usize) -> Vec<Vec<usize>> {
+        let mut win_lines: Vec<Vec<usize>> = Vec::new();
+
+        if k > n {
+            return win_lines;
+        }
+
+        // Rows
+        for r in 0..n {
+            for start_c in 0..=(n - k) {
+                win_lines.push((0..k).map(|i| r * n + start_c + i).collect());
+            }
+        }
+        // Columns
+        for c in 0..n {
+            for start_r in 0..=(n - k) {
+                win_lines.push((0..k).map(|i| (start_r + i) * n + c).collect());
+            }
+        }
+        // Diagonals (top-left to bottom-right)
+        for start_r in 0..=(n - k) {
+            for start_c in 0..=(n - k) {
+                win_lines.push((0..k).map(|i| (start_r + i) * n + start_c + i).collect());
+            }
+        }
+        // Diagonals (top-right to bottom-left)
+        for start_r in 0..=(n - k) {
+            for start_c in (k - 1)..n {
+                win_lines.push((0..k).map(|i| (start_r + i) * n + start_c - i).collect());
+            }
+        }
+        win_lines
+    }
 }
 
 impl Display for TicTacToe {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let mut str_board = String::new();
+        let separator = "-".repeat(4 * self.n - 1);
 
         for (i, cell) in self.board.iter().enumerate() {
-            if (i != 0) && (i % 3 == 0) {
-                str_board.push_str("\n-----------\n");
+            if (i != 0) && (i % self.n == 0) {
+                str_board.push_str(&format!("\n{}\n", separator));
             }
             str_board.push_str(format!(" {} ", cell).as_str());
 
-            if (i + 1) % 3 != 0 {
+            if (i + 1) % self.n != 0 {
                 str_board.push('|');
             }
         }
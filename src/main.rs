@@ -1,17 +1,73 @@
 mod menace;
 mod game;
+mod replay;
+mod mcts;
+mod format;
 
 use std::io::{Write, stdin, stdout};
-use game::{symbol::Symbol, tictactoe::TicTacToe};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use game::{symbol::Symbol, tictactoe::{State, TicTacToe}};
 use menace::Menace;
+use mcts::Mcts;
+
+/// The default save file, used unless a `--path` argument picks another.
+/// Ending in `.cbor` instead of `.json` switches MENACE to compact CBOR
+/// persistence; see `Format::from_path`.
+const DEFAULT_MENACE_PATH: &str = "menace.json";
+
+/// How often (in episodes) the batch modes export a replay. Exporting every
+/// game would dominate runtime over thousands of episodes, so only every
+/// `REPLAY_SAMPLE_INTERVAL`th game is written to `replays/`.
+const REPLAY_SAMPLE_INTERVAL: usize = 100;
 
 fn main() -> Result<(), std::io::Error> {
-    let mut game = TicTacToe::new();
-    let mut menace = match Menace::from_json("menace.json") {
+    let mut args: Vec<String> = std::env::args().collect();
+    let mut menace_path = DEFAULT_MENACE_PATH.to_string();
+
+    if let Some(flag_idx) = args.iter().position(|arg| arg == "--path") {
+        if let Some(value) = args.get(flag_idx + 1) {
+            menace_path = value.clone();
+            args.drain(flag_idx..=flag_idx + 1);
+        }
+    }
+
+    if args.get(1).map(String::as_str) == Some("selfplay") {
+        let episodes: usize = args.get(2)
+            .and_then(|arg| arg.parse().ok())
+            .unwrap_or(1000);
+        return self_play(episodes, &menace_path);
+    }
+    if args.get(1).map(String::as_str) == Some("mcts") {
+        let episodes: usize = args.get(2)
+            .and_then(|arg| arg.parse().ok())
+            .unwrap_or(1000);
+        let iterations: usize = args.get(3)
+            .and_then(|arg| arg.parse().ok())
+            .unwrap_or(200)
+            .max(1);
+        return mcts_benchmark(episodes, iterations, &menace_path);
+    }
+
+    let mut game = if args.get(1).map(String::as_str) == Some("play") {
+        let n: usize = args.get(2)
+            .and_then(|arg| arg.parse().ok())
+            .unwrap_or(3)
+            .max(1);
+        let k: usize = args.get(3)
+            .and_then(|arg| arg.parse().ok())
+            .unwrap_or(n)
+            .clamp(1, n);
+        TicTacToe::with_size(n, k).expect("clamped n/k are always valid")
+    } else {
+        TicTacToe::new()
+    };
+    let mut menace = match Menace::load(&menace_path) {
         Ok(menace) => menace,
         Err(_) => Menace::new()
     };
 
+    println!("Playing {}x{}, {} in a row to win\n", game.size(), game.size(), game.win_length());
     println!("\n{}\n", game);
     loop {
         if menace_turn(&mut game, &mut menace) {
@@ -26,7 +82,150 @@ fn main() -> Result<(), std::io::Error> {
             break;
         }
     }
-    menace.save_to_json()
+    menace.save(&menace_path)
+}
+
+/// Headless self-play mode: trains a `Menace` playing X against a `Menace`
+/// playing O for `episodes` games, printing running win/draw/loss rates as
+/// it goes. The X-side MENACE is loaded from `path` (the same file the
+/// interactive mode, where MENACE plays O, saves to) if it exists, so
+/// repeated runs accumulate on top of prior training, and is saved back to
+/// `path` at the end.
+fn self_play(episodes: usize, path: &str) -> Result<(), std::io::Error> {
+    let mut menace_x = Menace::load(path).unwrap_or_else(|_| Menace::new());
+    let mut menace_o = Menace::new();
+
+    let mut x_wins = 0usize;
+    let mut o_wins = 0usize;
+    let mut draws = 0usize;
+
+    for episode in 1..=episodes {
+        let mut game = TicTacToe::new();
+        let sample = episode % REPLAY_SAMPLE_INTERVAL == 0;
+
+        loop {
+            game.from_state(&menace_x.step(&game, Symbol::X).unwrap());
+
+            match game.state() {
+                State::XWon => {
+                    menace_x.train(menace::WIN_REWARD);
+                    menace_o.train(menace::LOSE_REWARD);
+                    x_wins += 1;
+                    if sample { save_replay(&menace_x); }
+                    break;
+                }
+                State::Draw => {
+                    menace_x.train(menace::DRAW_REWARD);
+                    menace_o.train(menace::DRAW_REWARD);
+                    draws += 1;
+                    if sample { save_replay(&menace_x); }
+                    break;
+                }
+                _ => {}
+            }
+
+            game.from_state(&menace_o.step(&game, Symbol::O).unwrap());
+
+            match game.state() {
+                State::OWon => {
+                    menace_o.train(menace::WIN_REWARD);
+                    menace_x.train(menace::LOSE_REWARD);
+                    o_wins += 1;
+                    if sample { save_replay(&menace_x); }
+                    break;
+                }
+                State::Draw => {
+                    menace_x.train(menace::DRAW_REWARD);
+                    menace_o.train(menace::DRAW_REWARD);
+                    draws += 1;
+                    if sample { save_replay(&menace_x); }
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        if episode % 100 == 0 {
+            println!(
+                "[{episode}] X wins: {:.1}% | O wins: {:.1}% | draws: {:.1}%",
+                x_wins as f64 / episode as f64 * 100.0,
+                o_wins as f64 / episode as f64 * 100.0,
+                draws as f64 / episode as f64 * 100.0
+            );
+        }
+    }
+
+    menace_x.save(path)
+}
+
+/// Pits the persisted MENACE (playing O) against a Monte Carlo Tree Search
+/// opponent (playing X, `iterations` rollouts per move) for `episodes`
+/// games, printing running win/draw/loss rates as it goes
+fn mcts_benchmark(episodes: usize, iterations: usize, path: &str) -> Result<(), std::io::Error> {
+    let mut menace = match Menace::load(path) {
+        Ok(menace) => menace,
+        Err(_) => Menace::new()
+    };
+
+    let mut menace_wins = 0usize;
+    let mut mcts_wins = 0usize;
+    let mut draws = 0usize;
+
+    for episode in 1..=episodes {
+        let mut game = TicTacToe::new();
+        let sample = episode % REPLAY_SAMPLE_INTERVAL == 0;
+
+        loop {
+            let mcts_move = Mcts::search(&game, Symbol::X, iterations)
+                .expect("mcts_benchmark always breaks before the board is terminal");
+            game.from_state(&mcts_move);
+
+            match game.state() {
+                State::XWon => {
+                    menace.train(menace::LOSE_REWARD);
+                    mcts_wins += 1;
+                    if sample { save_replay(&menace); }
+                    break;
+                }
+                State::Draw => {
+                    menace.train(menace::DRAW_REWARD);
+                    draws += 1;
+                    if sample { save_replay(&menace); }
+                    break;
+                }
+                _ => {}
+            }
+
+            game.from_state(&menace.step(&game, Symbol::O).unwrap());
+
+            match game.state() {
+                State::OWon => {
+                    menace.train(menace::WIN_REWARD);
+                    menace_wins += 1;
+                    if sample { save_replay(&menace); }
+                    break;
+                }
+                State::Draw => {
+                    menace.train(menace::DRAW_REWARD);
+                    draws += 1;
+                    if sample { save_replay(&menace); }
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        if episode % 100 == 0 {
+            println!(
+                "[{episode}] MENACE wins: {:.1}% | MCTS wins: {:.1}% | draws: {:.1}%",
+                menace_wins as f64 / episode as f64 * 100.0,
+                mcts_wins as f64 / episode as f64 * 100.0,
+                draws as f64 / episode as f64 * 100.0
+            );
+        }
+    }
+
+    menace.save(path)
 }
 
 fn menace_turn(game: &mut TicTacToe, menace: &mut Menace) -> bool {
@@ -34,17 +233,36 @@ fn menace_turn(game: &mut TicTacToe, menace: &mut Menace) -> bool {
     game.from_state(&menace.step(&game, Symbol::O).unwrap());
     println!("\n{}\n", game);
 
-    if game.is_winner(Symbol::O) {
-        println!("O wins!\n");
-        menace.train(menace::WIN_REWARD);
-        return true
+    match game.state() {
+        State::OWon => {
+            println!("O wins!\n");
+            menace.train(menace::WIN_REWARD);
+            save_replay(menace);
+            true
+        }
+        State::Draw => {
+            println!("Draw!\n");
+            menace.train(menace::DRAW_REWARD);
+            save_replay(menace);
+            true
+        }
+        _ => false
     }
-    else if game.is_draw() {
-        println!("Draw!\n");
-        menace.train(menace::DRAW_REWARD);
-        return true
+}
+
+/// Exports the game MENACE just finished training on to a uniquely-named
+/// JSON file under `replays/`. Called after every game in interactive mode,
+/// and every `REPLAY_SAMPLE_INTERVAL`th game in the batch modes.
+fn save_replay(menace: &Menace) {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before the Unix epoch")
+        .as_nanos();
+    let path = format!("replays/game_{}.json", millis);
+
+    if let Err(err) = menace.export_replay(Path::new(&path)) {
+        eprintln!("Failed to save replay: {}", err);
     }
-    false
 }
 
 fn player_turn(game: &mut TicTacToe, menace: &mut Menace) -> (bool, bool) {
@@ -58,20 +276,29 @@ fn player_turn(game: &mut TicTacToe, menace: &mut Menace) -> (bool, bool) {
             return (false, true);
         }
 
-        let player_action: u8 = {
+        let player_action: usize = {
             let values: Vec<&str> = line.trim().split(',').collect();
-            let row: u8 = values[0].to_string().parse().unwrap();
-            let col: u8 = values[1].to_string().parse().unwrap();
-            row * 3 + col
+            let row: usize = values[0].to_string().parse().unwrap();
+            let col: usize = values[1].to_string().parse().unwrap();
+            row * game.size() + col
         };
 
         game.place_piece(Symbol::X, player_action).unwrap();
         println!("\n{}\n", game);
 
-        if game.is_winner(Symbol::X) {
-            println!("X wins!\n");
-            menace.train(menace::LOSE_REWARD);
-            return (true, false);
+        match game.state() {
+            State::XWon => {
+                println!("X wins!\n");
+                menace.train(menace::LOSE_REWARD);
+                save_replay(menace);
+                (true, false)
+            }
+            State::Draw => {
+                println!("Draw!\n");
+                menace.train(menace::DRAW_REWARD);
+                save_replay(menace);
+                (true, false)
+            }
+            _ => (false, false)
         }
-        (false, false)
 }
@@ -0,0 +1,182 @@
+use rand::{prelude::SliceRandom, thread_rng};
+use crate::game::{symbol::Symbol, tictactoe::{State, TicTacToe}};
+
+const EXPLORATION: f64 = 1.41;
+
+fn opponent(piece: Symbol) -> Symbol {
+    match piece {
+        Symbol::X => Symbol::O,
+        Symbol::O => Symbol::X,
+        Symbol::EMPTY => Symbol::EMPTY
+    }
+}
+
+/// The reward of a finished game from `mover`'s perspective: `1.0` for a
+/// win, `0.5` for a draw, `0.0` for a loss or an unfinished game
+fn reward_for(mover: Symbol, outcome: State) -> f64 {
+    match outcome {
+        State::XWon if mover == Symbol::X => 1.0,
+        State::OWon if mover == Symbol::O => 1.0,
+        State::Draw => 0.5,
+        _ => 0.0
+    }
+}
+
+/// A single node in the search tree: the board state it represents, whose
+/// turn it is to move from here, and the UCT statistics accumulated for it
+struct Node {
+    state: String,
+    to_move: Symbol,
+    n: u32,
+    w: f64,
+    children: Vec<usize>,
+    untried: Vec<String>
+}
+
+impl Node {
+    fn new(state: String, to_move: Symbol, untried: Vec<String>) -> Self {
+        Self {
+            state,
+            to_move,
+            n: 0,
+            w: 0.0,
+            children: Vec::new(),
+            untried
+        }
+    }
+
+    fn uct(&self, parent_n: u32) -> f64 {
+        if self.n == 0 {
+            return f64::INFINITY;
+        }
+        (self.w / self.n as f64) + EXPLORATION * ((parent_n as f64).ln() / self.n as f64).sqrt()
+    }
+}
+
+/// A Monte Carlo Tree Search opponent, built fresh for each move via
+/// [`Mcts::search`]. Runs UCT (selection, expansion, simulation,
+/// backpropagation) to pick the strongest next state it can find within
+/// the given iteration budget.
+pub struct Mcts<'a> {
+    template: &'a TicTacToe,
+    nodes: Vec<Node>
+}
+
+impl<'a> Mcts<'a> {
+    /// Runs `iterations` rounds of UCT from `game`'s current state and
+    /// returns the flattened next state judged strongest for `piece`
+    ///
+    /// # Returns
+    ///
+    /// `None` if `game` has no legal moves left for `piece` (the board is
+    /// already terminal), `Some(next_state)` otherwise
+    pub fn search(game: &'a TicTacToe, piece: Symbol, iterations: usize) -> Option<String> {
+        let untried = game.next_states(piece);
+
+        if untried.is_empty() {
+            return None;
+        }
+
+        let root = Node::new(game.flat(), piece, untried);
+        let mut mcts = Self {
+            template: game,
+            nodes: vec![root]
+        };
+
+        for _ in 0..iterations {
+            mcts.iterate();
+        }
+        Some(mcts.best_move())
+    }
+
+    fn iterate(&mut self) {
+        let mut path = vec![0usize];
+        let mut node_idx = 0;
+
+        while self.nodes[node_idx].untried.is_empty() && !self.nodes[node_idx].children.is_empty() {
+            node_idx = self.select_child(node_idx);
+            path.push(node_idx);
+        }
+
+        if !self.nodes[node_idx].untried.is_empty() {
+            node_idx = self.expand(node_idx);
+            path.push(node_idx);
+        }
+
+        let outcome = self.simulate(node_idx);
+        self.backpropagate(&path, outcome);
+    }
+
+    fn select_child(&self, node_idx: usize) -> usize {
+        let parent_n = self.nodes[node_idx].n;
+
+        self.nodes[node_idx].children.iter()
+            .copied()
+            .max_by(|&a, &b| self.nodes[a].uct(parent_n).partial_cmp(&self.nodes[b].uct(parent_n)).unwrap())
+            .unwrap()
+    }
+
+    fn expand(&mut self, node_idx: usize) -> usize {
+        let next_state = self.nodes[node_idx].untried.pop().unwrap();
+        let to_move = opponent(self.nodes[node_idx].to_move);
+
+        let mut child_game = self.template.clone();
+        child_game.from_state(&next_state);
+
+        let untried = match child_game.state() {
+            State::XWon | State::OWon | State::Draw => Vec::new(),
+            _ => child_game.next_states(to_move)
+        };
+
+        let child_idx = self.nodes.len();
+        self.nodes.push(Node::new(next_state, to_move, untried));
+        self.nodes[node_idx].children.push(child_idx);
+        child_idx
+    }
+
+    fn simulate(&self, node_idx: usize) -> State {
+        let node = &self.nodes[node_idx];
+        let mut sim_game = self.template.clone();
+        sim_game.from_state(&node.state);
+
+        let mut to_move = node.to_move;
+        let mut rng = thread_rng();
+
+        loop {
+            match sim_game.state() {
+                State::XMove | State::OMove => {}
+                terminal => return terminal
+            }
+            let next_states = sim_game.next_states(to_move);
+            let choice = next_states.choose(&mut rng).unwrap();
+
+            sim_game.from_state(choice);
+            to_move = opponent(to_move);
+        }
+    }
+
+    fn backpropagate(&mut self, path: &[usize], outcome: State) {
+        for &node_idx in path.iter().rev() {
+            let node = &mut self.nodes[node_idx];
+            let mover = opponent(node.to_move);
+
+            node.n += 1;
+            node.w += reward_for(mover, outcome);
+        }
+    }
+
+    /// # Returns
+    ///
+    /// The state of the most-visited child of the root. If `search` ran too
+    /// few iterations to expand any child, falls back to an untried move
+    /// picked at random.
+    fn best_move(&self) -> String {
+        self.nodes[0].children.iter()
+            .max_by_key(|&&child| self.nodes[child].n)
+            .map(|&child| self.nodes[child].state.clone())
+            .unwrap_or_else(|| {
+                let mut rng = thread_rng();
+                self.nodes[0].untried.choose(&mut rng).unwrap().clone()
+            })
+    }
+}
@@ -1,7 +1,9 @@
 use std::{collections::{HashMap, LinkedList}, fs::File, path::Path};
 use rand::{prelude::SliceRandom, thread_rng};
 use serde::{Deserialize, Serialize};
-use crate::game::{symbol::Symbol, tictactoe::TicTacToe};
+use crate::format::Format;
+use crate::game::{symbol::Symbol, tictactoe::{State, TicTacToe}};
+use crate::replay::{Replay, Turn};
 use serde_json;
 use std::io::prelude::*;
 
@@ -13,7 +15,7 @@ const DEFAULT_BEAD_COUNT: usize = 3;
 const MIN_BEAD_COUNT: i32 = 1;
 
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
-struct Bead {
+pub(crate) struct Bead {
     next_state: String,
     count: usize
 }
@@ -30,89 +32,160 @@ impl Bead {
 #[derive(Serialize, Deserialize)]
 pub struct Menace {
     beads: HashMap<String, Vec<Bead>>,
-    #[serde(skip_serializing)]
-    episode: LinkedList<(String, Bead)>
+    #[serde(skip)]
+    episode: LinkedList<(String, String, String, Symbol, Bead)>,
+    #[serde(skip)]
+    last_replay: Option<Replay>
 }
 
 impl Menace {
     pub fn new() -> Self {
         Self {
             beads: HashMap::new(),
-            episode: LinkedList::new()
+            episode: LinkedList::new(),
+            last_replay: None
         }
     }
 
-    /// Creates a new MENACE from the contents of a JSON file
-    pub fn from_json(filename: &str) -> Result<Self, String> {
-        let mut file = match File::open(filename) {
+    /// Creates a new MENACE from the contents of a file at `path`, using
+    /// the serialization backend inferred from its extension
+    pub fn load(path: &str) -> Result<Self, String> {
+        let mut file = match File::open(path) {
             Ok(file) => file,
             Err(err) => return Err(err.to_string())
         };
-        let mut file_contents = String::new();
-        file.read_to_string(&mut file_contents)
-            .expect("Error reading file contents");
 
-        match serde_json::from_str::<Self>(file_contents.as_str()) {
-            Ok(result) => Ok(result),
-            Err(err) => Err(err.to_string())
+        match Format::from_path(path) {
+            Format::Json => {
+                let mut file_contents = String::new();
+                file.read_to_string(&mut file_contents)
+                    .expect("Error reading file contents");
+
+                serde_json::from_str::<Self>(file_contents.as_str())
+                    .map_err(|err| err.to_string())
+            }
+            Format::Cbor => serde_cbor::from_reader(file).map_err(|err| err.to_string())
         }
     }
 
-    /// Saves the current state of MENACE to a JSON file
-    pub fn save_to_json(&self) -> Result<(), std::io::Error> {
-        let mut file = File::create(&Path::new("menace.json")).unwrap();
-        let menace_json = serde_json::to_string_pretty(self).unwrap();
+    /// Saves the current state of MENACE to a file at `path`, using the
+    /// serialization backend inferred from its extension
+    pub fn save(&self, path: &str) -> Result<(), std::io::Error> {
+        let file = File::create(path)?;
+
+        match Format::from_path(path) {
+            Format::Json => {
+                let menace_json = serde_json::to_string_pretty(self).unwrap();
+                (&file).write_all(menace_json.as_bytes())
+            }
+            Format::Cbor => serde_cbor::to_writer(file, self)
+                .map_err(std::io::Error::other)
+        }
+    }
 
-        file.write_all(menace_json.as_bytes())
+    /// Exports the most recently completed game (recorded the last time
+    /// `train` was called) as a JSON replay to `path`
+    ///
+    /// # Returns
+    ///
+    /// An error if no game has been trained yet, or if writing `path` fails
+    pub fn export_replay(&self, path: &Path) -> std::io::Result<()> {
+        match &self.last_replay {
+            Some(replay) => replay.save(path),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound, "no replay available to export"
+            ))
+        }
     }
 
     /// Picks a weighted random next state state for the game based on MENACE's
     /// bead counts for current state. If no beads exist for the current state,
     /// the game's next possible states are calulated and MENACE's bead counts
     /// for these states are set to the default values.
-    /// 
-    /// The next state chosen by MENACE is recorded in 'episode' to keep
-    /// track of MENACE's moves from the last game played so it can be
-    /// trained appropriately
+    ///
+    /// States are keyed by their canonical form so that the 8 rotations and
+    /// reflections of a board all share the same matchbox, and the bead it
+    /// picks is mapped back out of canonical form before being returned.
+    ///
+    /// The move is recorded in 'episode' in the board's actual orientation
+    /// (not the canonical one used to look up beads) to keep track of
+    /// MENACE's moves from the last game played, so it can be trained
+    /// appropriately and the replay it produces reads as one evolving game
     ///
     /// # Returns
-    /// 
+    ///
     /// 'None' if the game has no possible next state to go to,
     /// 'Some(next_state)' otherwise
     pub fn step(&mut self, game: &TicTacToe, piece: Symbol) -> Option<String> {
-        if game.is_winner(Symbol::X) || game.is_winner(Symbol::O) || game.is_draw() {
-            return None;
+        match game.state() {
+            State::XWon | State::OWon | State::Draw => return None,
+            _ => {}
         }
 
-        let state: String = game.flat();
+        let (state, perm) = game.canonical();
         let mut rng = thread_rng();
 
         if !self.beads.contains_key(&state) {
-            let next_states: Vec<String> = game.next_states(piece);
+            let next_states: Vec<String> = game.next_states(piece).iter()
+                .map(|next_state| Self::apply_perm(next_state, &perm))
+                .collect();
 
             self.beads.insert(state.clone(), next_states.iter()
-                .map(|next_state| 
+                .map(|next_state|
                     Bead::new(next_state.clone(), DEFAULT_BEAD_COUNT)
                 ).collect()
             );
         }
-        let bead: &Bead = self.beads[&state].choose_weighted(&mut rng, 
+        let bead: &Bead = self.beads[&state].choose_weighted(&mut rng,
             |bead| bead.count
         ).unwrap();
+        let chosen = Self::apply_perm(&bead.next_state, &Self::invert_perm(&perm));
 
-        self.episode.push_front((state, bead.clone()));
-        Some(bead.next_state.clone())
+        self.episode.push_front((state, game.flat(), chosen.clone(), piece, bead.clone()));
+        Some(chosen)
+    }
+
+    /// Applies an index permutation produced by `TicTacToe::canonical` to a
+    /// flattened board state, reordering it into the corresponding orientation
+    fn apply_perm(flat: &str, perm: &[usize]) -> String {
+        let bytes = flat.as_bytes();
+        perm.iter().map(|&i| bytes[i] as char).collect()
+    }
+
+    /// Computes the functional inverse of an index permutation, i.e. the
+    /// permutation that undoes it
+    fn invert_perm(perm: &[usize]) -> Vec<usize> {
+        let mut inverse = vec![0usize; perm.len()];
+
+        for (i, &p) in perm.iter().enumerate() {
+            inverse[p] = i;
+        }
+        inverse
     }
 
     /// MENACE "learns" by updating the bead counts for the state transitions
-    /// it chose during the most recent game (represented by 'episode')
+    /// it chose during the most recent game (represented by 'episode'), and
+    /// records the game as a replay, retrievable via `export_replay`
     pub fn train(&mut self, delta: i32) {
+        let outcome = match delta {
+            WIN_REWARD => "win",
+            LOSE_REWARD => "loss",
+            DRAW_REWARD => "draw",
+            _ => "unknown"
+        };
+        let mut turns: Vec<Turn> = Vec::with_capacity(self.episode.len());
+
         loop {
-            let (state, bead) = match self.episode.pop_front() {
-                Some(state_bead_pair) => state_bead_pair,
+            let (canonical_state, actual_state, actual_chosen, piece, bead) = match self.episode.pop_front() {
+                Some(entry) => entry,
                 None => break
             };
-            let beads_for_state: &mut Vec<Bead> = self.beads.get_mut(&state).unwrap();
+            let beads_for_state: &mut Vec<Bead> = self.beads.get_mut(&canonical_state).unwrap();
+
+            turns.push(Turn::new(
+                actual_state, piece.as_char(), beads_for_state.clone(), actual_chosen
+            ));
+
             let bead_index: usize = beads_for_state.iter().position(
                 |el| *el == bead
             ).unwrap();
@@ -123,5 +196,8 @@ impl Menace {
             prev_count = (prev_count + delta).max(MIN_BEAD_COUNT);
             bead.count = prev_count as usize
         }
+        turns.reverse();
+
+        self.last_replay = Some(Replay::new(turns, outcome, delta));
     }
 }
\ No newline at end of file
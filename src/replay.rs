@@ -0,0 +1,61 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use serde::Serialize;
+use crate::menace::Bead;
+
+/// A single decision MENACE made during a game: the state it saw, the
+/// piece it was playing, the bead counts it weighed, and the state it
+/// chose to move to
+#[derive(Serialize)]
+pub(crate) struct Turn {
+    state: String,
+    piece: char,
+    beads: Vec<Bead>,
+    chosen: String
+}
+
+impl Turn {
+    pub(crate) fn new(state: String, piece: char, beads: Vec<Bead>, chosen: String) -> Self {
+        Self {
+            state,
+            piece,
+            beads,
+            chosen
+        }
+    }
+}
+
+/// The full move-by-move record of a single game MENACE played, along
+/// with how it ended
+#[derive(Serialize)]
+pub(crate) struct Replay {
+    turns: Vec<Turn>,
+    outcome: String,
+    reward: i32
+}
+
+impl Replay {
+    pub(crate) fn new(turns: Vec<Turn>, outcome: &str, reward: i32) -> Self {
+        Self {
+            turns,
+            outcome: outcome.to_string(),
+            reward
+        }
+    }
+
+    /// Serializes this replay as JSON to `path`, creating its parent
+    /// directory if it doesn't already exist
+    pub(crate) fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        let mut file = File::create(path)?;
+        let replay_json = serde_json::to_string_pretty(self).unwrap();
+
+        file.write_all(replay_json.as_bytes())
+    }
+}